@@ -1,15 +1,14 @@
 use criterion::{
-    BenchmarkId, Criterion, Throughput, black_box, criterion_group,
-    criterion_main, PlotConfiguration, AxisScale
+    AxisScale, BatchSize, BenchmarkId, Criterion, PlotConfiguration, Throughput, black_box,
+    criterion_group, criterion_main,
 };
 
-
-use::criterion::BenchmarkGroup
-
+use rustpython_compiler::Mode as CompileMode;
+use rustpython_vm::{AsObject, Interpreter, Settings};
 
 use std::collections::HashMap;
 use std::path::Path;
-use pyo3::types::PyAnyMethods;
+use pyo3::types::{PyAnyMethods, PyDictMethods};
 
 // Constants for benchmark modes
 const MODE_CPYTHON: &str = "cpython";
@@ -21,6 +20,11 @@ const TEST_NAMES: [&str; 3] = [
     "execution"
 ];
 
+// Number of Pystone loops to run per iteration. This is the same order of magnitude CPython's
+// own `pystone.py` uses by default, and is large enough that process/VM startup cost doesn't
+// dominate the measured pystones/sec figure.
+const PYSTONE_LOOPS: u64 = 50_000;
+
 
 
 struct BenchmarkLocalGroup {
@@ -51,13 +55,13 @@ impl BenchmarkLocalGroup {
 
     fn run_parse_benchmarks(&self, c: &mut Criterion) {
         let mut parse_group = c.benchmark_group(TEST_NAMES[0]);
-        
+
         parse_group.plot_config(PlotConfiguration::default()
             .summary_scale(AxisScale::Logarithmic) );
-            
+
         for (name, contents) in &self.benches {
             parse_group.throughput(Throughput::Bytes(contents.len() as u64));
-            
+
             if self.mode == MODE_RUSTPYTHON {
                 parse_group.bench_with_input(
                     BenchmarkId::new(MODE_RUSTPYTHON, name),
@@ -67,7 +71,7 @@ impl BenchmarkLocalGroup {
                     }
                 );
             }
-            
+
             if self.mode == MODE_CPYTHON {
                 parse_group.bench_with_input(
                     BenchmarkId::new(MODE_CPYTHON, name),
@@ -92,10 +96,163 @@ impl BenchmarkLocalGroup {
         parse_group.finish();
     }
 
+    // Compile each benchmark file once, then time just the execution of the compiled code:
+    // `vm.run_code_obj` for RustPython, `exec(code, ...)` for CPython.
+    fn run_execution_benchmarks(&self, c: &mut Criterion) {
+        let mut exec_group = c.benchmark_group(TEST_NAMES[2]);
+
+        exec_group.plot_config(PlotConfiguration::default()
+            .summary_scale(AxisScale::Logarithmic) );
+
+        for (name, contents) in &self.benches {
+            exec_group.throughput(Throughput::Bytes(contents.len() as u64));
 
+            if self.mode == MODE_RUSTPYTHON {
+                let mut settings = Settings::default();
+                settings.path_list.push("Lib/".to_string());
+                settings.write_bytecode = false;
+                settings.user_site_directory = false;
+
+                Interpreter::with_init(settings, |vm| {
+                    for (name, init) in rustpython_stdlib::get_module_inits() {
+                        vm.add_native_module(name, init);
+                    }
+                })
+                .enter(|vm| {
+                    let code = vm
+                        .compile(contents, CompileMode::Exec, name.to_owned())
+                        .expect("Failed to compile code");
+
+                    exec_group.bench_with_input(
+                        BenchmarkId::new(MODE_RUSTPYTHON, name),
+                        &code,
+                        |b, code| {
+                            b.iter_batched(
+                                || vm.new_scope_with_builtins(),
+                                |scope| {
+                                    vm.run_code_obj(code.clone(), scope)
+                                        .expect("Error running benchmark code")
+                                },
+                                BatchSize::LargeInput,
+                            );
+                        },
+                    );
+                });
+            }
+
+            if self.mode == MODE_CPYTHON {
+                pyo3::Python::with_gil(|py| {
+                    let builtins = pyo3::types::PyModule::import(py, "builtins")
+                        .expect("Failed to import builtins");
+                    let compile = builtins.getattr("compile").expect("no compile in builtins");
+                    let exec = builtins.getattr("exec").expect("no exec in builtins");
+                    let code = compile
+                        .call1((contents, name, "exec"))
+                        .expect("Failed to compile code");
+
+                    exec_group.bench_with_input(
+                        BenchmarkId::new(MODE_CPYTHON, name),
+                        &code,
+                        |b, code| {
+                            b.iter_batched(
+                                || (pyo3::types::PyDict::new(py), pyo3::types::PyDict::new(py)),
+                                |(globals, locals)| {
+                                    exec.call((code, &globals, &locals), None)
+                                        .expect("Error running benchmark code")
+                                },
+                                BatchSize::LargeInput,
+                            );
+                        },
+                    );
+                });
+            }
+        }
+        exec_group.finish();
+    }
+
+    // Run the classic Pystone workload found at `pystone.py` in the benchmark directory and
+    // report its loop count via `Throughput::Elements`, so Criterion's own `Elements/s` figure
+    // is the pystone rate over the whole `pystones()` call. That call also runs the empty
+    // calibration loop which `Proc0` subtracts from its own figure, so Criterion's rate is
+    // somewhat lower than the one `pystones()` returns.
+    fn run_pystone_benchmarks(&self, c: &mut Criterion) {
+        let Some(contents) = self.benches.get("pystone.py") else {
+            return;
+        };
+
+        let mut pystone_group = c.benchmark_group(TEST_NAMES[1]);
+        pystone_group.throughput(Throughput::Elements(PYSTONE_LOOPS));
+
+        if self.mode == MODE_RUSTPYTHON {
+            let mut settings = Settings::default();
+            settings.path_list.push("Lib/".to_string());
+            settings.write_bytecode = false;
+            settings.user_site_directory = false;
+
+            Interpreter::with_init(settings, |vm| {
+                for (name, init) in rustpython_stdlib::get_module_inits() {
+                    vm.add_native_module(name, init);
+                }
+            })
+            .enter(|vm| {
+                let code = vm
+                    .compile(contents, CompileMode::Exec, "pystone.py".to_owned())
+                    .expect("Failed to compile pystone.py");
+                let scope = vm.new_scope_with_builtins();
+                vm.run_code_obj(code, scope.clone())
+                    .expect("Error running pystone.py");
+                let pystones = scope
+                    .globals
+                    .get_item("pystones", vm)
+                    .expect("pystone.py must define a pystones() function");
+
+                pystone_group.bench_function(BenchmarkId::new(MODE_RUSTPYTHON, "pystone"), |b| {
+                    b.iter(|| {
+                        pystones
+                            .call((PYSTONE_LOOPS,), vm)
+                            .expect("pystones() raised an exception")
+                    });
+                });
+            });
+        }
+
+        if self.mode == MODE_CPYTHON {
+            pyo3::Python::with_gil(|py| {
+                let builtins = pyo3::types::PyModule::import(py, "builtins")
+                    .expect("Failed to import builtins");
+                let compile = builtins.getattr("compile").expect("no compile in builtins");
+                let exec = builtins.getattr("exec").expect("no exec in builtins");
+                let code = compile
+                    .call1((contents, "pystone.py", "exec"))
+                    .expect("Failed to compile pystone.py");
+
+                // Run the module with a single namespace so its top-level definitions land in
+                // `globals`, where both the lookup below and the functions themselves find them.
+                let globals = pyo3::types::PyDict::new(py);
+                exec.call((&code, &globals), None)
+                    .expect("Error running pystone.py");
+                let pystones = globals
+                    .get_item("pystones")
+                    .expect("lookup failed")
+                    .expect("pystone.py must define a pystones() function");
+
+                pystone_group.bench_function(BenchmarkId::new(MODE_CPYTHON, "pystone"), |b| {
+                    b.iter(|| {
+                        pystones
+                            .call1((PYSTONE_LOOPS,))
+                            .expect("pystones() raised an exception")
+                    });
+                });
+            });
+        }
+
+        pystone_group.finish();
+    }
 
     fn run_all_benchmarks(&self, c: &mut Criterion) {
         self.run_parse_benchmarks(c);
+        self.run_execution_benchmarks(c);
+        self.run_pystone_benchmarks(c);
     }
 }
 
@@ -121,4 +278,4 @@ criterion_group!(
 
 
 
-criterion_main!(benches);
\ No newline at end of file
+criterion_main!(benches);
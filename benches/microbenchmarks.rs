@@ -1,24 +1,30 @@
 use criterion::{
-    BatchSize, BenchmarkGroup, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main,
-    measurement::WallTime,
+    AxisScale, BatchSize, BenchmarkGroup, BenchmarkId, Criterion, PlotConfiguration, SamplingMode,
+    Throughput, criterion_group, criterion_main, measurement::WallTime,
 };
 use pyo3::types::PyAnyMethods;
 use rustpython_compiler::Mode;
 use rustpython_vm::{AsObject, Interpreter, PyResult, Settings};
 use std::{
     fs, io,
+    io::Write as _,
     path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
 };
 
 // List of microbenchmarks to skip.
 //
-// These result in excessive memory usage, some more so than others. For example, while
-// exception_context.py consumes a lot of memory, it still finishes. On the other hand,
+// These result in excessive memory usage, some more so than others. On the other hand,
 // call_kwargs.py seems like it performs an excessive amount of allocations and results in
 // a system freeze.
 // In addition, the fact that we don't yet have a GC means that benchmarks which might consume
 // a bearable amount of memory accumulate. As such, best to skip them for now.
-const SKIP_MICROBENCHMARKS: [&str; 8] = [
+//
+// exception_context.py used to be here too, but it only needed a smaller, flat-sampled
+// Criterion budget rather than not running at all — see its `# criterion: ...` directive,
+// handled by `CriterionConfig` below.
+const SKIP_MICROBENCHMARKS: [&str; 7] = [
     "call_simple.py",
     "call_kwargs.py",
     "construct_object.py",
@@ -26,7 +32,6 @@ const SKIP_MICROBENCHMARKS: [&str; 8] = [
     "define_class.py",
     "exception_nested.py",
     "exception_simple.py",
-    "exception_context.py",
 ];
 
 // Struct representing a single microbenchmark
@@ -35,6 +40,135 @@ pub struct MicroBenchmark {
     setup: String,    // Setup code to run before the main code (optional)
     code: String,     // The main code to benchmark
     iterate: bool,    // Whether to run the code multiple times with different iteration counts
+    config: CriterionConfig, // Per-benchmark Criterion overrides parsed from a header directive
+    path: PathBuf,    // On-disk location of the original file, for subprocess benchmarking
+}
+
+// An external interpreter executable to benchmark as a whole subprocess: (label, path, args).
+// Unlike the in-process PyO3/RustPython paths below, this captures startup + import cost,
+// and isn't locked to whatever libpython PyO3 happens to link against. Entries whose
+// executable isn't found (or that fail a dry run) are skipped rather than failing the run.
+struct InterpreterTarget {
+    label: &'static str,
+    path: &'static str,
+    args: &'static [&'static str],
+}
+
+const INTERPRETER_TARGETS: &[InterpreterTarget] = &[
+    InterpreterTarget {
+        label: "python3",
+        path: "python3",
+        args: &[],
+    },
+    InterpreterTarget {
+        label: "pypy3",
+        path: "pypy3",
+        args: &[],
+    },
+    InterpreterTarget {
+        label: "rustpython-release",
+        path: "target/release/rustpython",
+        args: &[],
+    },
+];
+
+// Per-benchmark Criterion tuning, parsed from an optional header directive of the form
+//
+//   # criterion: sample_size=30, measurement_time=12s, warm_up_time=3s, sampling_mode=flat, noise_threshold=0.05
+//
+// Any key left unspecified keeps Criterion's own default. This lets individually expensive
+// benchmarks (e.g. ones that would otherwise need to go in SKIP_MICROBENCHMARKS) run with a
+// reduced sample size and flat sampling instead of not running at all.
+#[derive(Default)]
+struct CriterionConfig {
+    sample_size: Option<usize>,
+    measurement_time: Option<Duration>,
+    warm_up_time: Option<Duration>,
+    sampling_mode: Option<SamplingMode>,
+    noise_threshold: Option<f64>,
+    confidence_level: Option<f64>,
+    significance_level: Option<f64>,
+}
+
+impl CriterionConfig {
+    // Look for a `# criterion: ...` directive line in the file's header (the part above the
+    // `# ---` split, or the whole file if there is no split) and parse its `key=value` pairs.
+    // Unknown keys and unparsable values are ignored so a typo degrades to defaults rather
+    // than failing the whole benchmark run. Only the header is scanned so a benchmark body
+    // that happens to contain a matching comment isn't mistaken for a directive.
+    fn parse(contents: &str) -> Self {
+        let mut config = CriterionConfig::default();
+        let header = contents.split("# ---").next().unwrap_or(contents);
+        let Some(line) = header
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("# criterion:"))
+        else {
+            return config;
+        };
+
+        for entry in line.split(',') {
+            let entry = entry.trim();
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "sample_size" => config.sample_size = value.parse().ok(),
+                "measurement_time" => config.measurement_time = parse_duration(value),
+                "warm_up_time" => config.warm_up_time = parse_duration(value),
+                "sampling_mode" => {
+                    config.sampling_mode = match value {
+                        "linear" => Some(SamplingMode::Linear),
+                        "flat" => Some(SamplingMode::Flat),
+                        _ => None,
+                    }
+                }
+                "noise_threshold" => config.noise_threshold = value.parse().ok(),
+                "confidence_level" => config.confidence_level = value.parse().ok(),
+                "significance_level" => config.significance_level = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        config
+    }
+
+    // Apply whichever fields were set to the benchmark group, leaving the rest at
+    // Criterion's defaults.
+    fn apply(&self, group: &mut BenchmarkGroup<WallTime>) {
+        if let Some(sample_size) = self.sample_size {
+            group.sample_size(sample_size);
+        }
+        if let Some(measurement_time) = self.measurement_time {
+            group.measurement_time(measurement_time);
+        }
+        if let Some(warm_up_time) = self.warm_up_time {
+            group.warm_up_time(warm_up_time);
+        }
+        if let Some(sampling_mode) = self.sampling_mode {
+            group.sampling_mode(sampling_mode);
+        }
+        if let Some(noise_threshold) = self.noise_threshold {
+            group.noise_threshold(noise_threshold);
+        }
+        if let Some(confidence_level) = self.confidence_level {
+            group.confidence_level(confidence_level);
+        }
+        if let Some(significance_level) = self.significance_level {
+            group.significance_level(significance_level);
+        }
+    }
+}
+
+// Parse a duration like "12s" or "500ms" as used in a `# criterion:` directive.
+fn parse_duration(value: &str) -> Option<Duration> {
+    if let Some(millis) = value.strip_suffix("ms") {
+        millis.parse().ok().map(Duration::from_millis)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        secs.parse::<f64>().ok().map(Duration::from_secs_f64)
+    } else {
+        None
+    }
 }
 
 // Run the benchmark using CPython (via PyO3)
@@ -77,16 +211,23 @@ fn bench_cpython_code(group: &mut BenchmarkGroup<WallTime>, bench: &MicroBenchma
             (globals, locals)
         };
 
-        // If the benchmark is iterative, run it with different iteration counts
+        // If the benchmark is iterative, run it with different iteration counts. Build the
+        // scope once per Criterion sample and drive `iters` calls to `exec` inside a single
+        // Instant::now()/elapsed() pair (`Bencher::iter_custom`), instead of re-entering
+        // `exec` once per Criterion iteration — that way the measured time reflects the
+        // Python body's steady-state cost, not scope setup or the Rust<->interpreter boundary.
         if bench.iterate {
             for idx in (100..=1_000).step_by(200) {
                 group.throughput(Throughput::Elements(idx as u64));
                 group.bench_with_input(BenchmarkId::new("cpython", &bench.name), &idx, |b, idx| {
-                    b.iter_batched_ref(
-                        || bench_setup(Some(*idx)),
-                        bench_func,
-                        BatchSize::LargeInput,
-                    );
+                    b.iter_custom(|iters| {
+                        let mut scope = bench_setup(Some(*idx));
+                        let start = Instant::now();
+                        for _ in 0..iters {
+                            bench_func(&mut scope);
+                        }
+                        start.elapsed()
+                    });
                 });
             }
         } else {
@@ -154,7 +295,10 @@ fn bench_rustpython_code(group: &mut BenchmarkGroup<WallTime>, bench: &MicroBenc
             scope
         };
 
-        // If the benchmark is iterative, run it with different iteration counts
+        // If the benchmark is iterative, run it with different iteration counts. As on the
+        // CPython side, build the scope once per sample, then run the compiled bytecode
+        // `iters` times in a tight loop timed by a single Instant pair, giving a steady-state
+        // measurement of `run_code_obj` itself instead of one dominated by per-call setup.
         if bench.iterate {
             for idx in (100..=1_000).step_by(200) {
                 group.throughput(Throughput::Elements(idx as u64));
@@ -162,11 +306,14 @@ fn bench_rustpython_code(group: &mut BenchmarkGroup<WallTime>, bench: &MicroBenc
                     BenchmarkId::new("rustpython", &bench.name),
                     &idx,
                     |b, idx| {
-                        b.iter_batched(
-                            || bench_setup(Some(*idx)),
-                            bench_func,
-                            BatchSize::LargeInput,
-                        );
+                        b.iter_custom(|iters| {
+                            let scope = bench_setup(Some(*idx));
+                            let start = Instant::now();
+                            for _ in 0..iters {
+                                bench_func(scope.clone());
+                            }
+                            start.elapsed()
+                        });
                     },
                 );
             }
@@ -179,18 +326,179 @@ fn bench_rustpython_code(group: &mut BenchmarkGroup<WallTime>, bench: &MicroBenc
     })
 }
 
+// Benchmark each configured external interpreter as a whole subprocess, modeled on
+// Criterion's external-program benchmarking: the whole process (startup + import +
+// execution) is timed, rather than just the inner VM call.
+//
+// Only runs non-iterative benchmarks: iterative files rely on the in-process harness to
+// inject an `ITERATIONS` global before running, which a standalone `interpreter file.py`
+// invocation never gets, so they'd just raise NameError.
+//
+// Before registering a (interpreter, benchmark) pair with Criterion, we run it once as a
+// dry run. If the interpreter is missing or the dry run fails, the pair is skipped entirely
+// instead of being registered — a benchmark that reports a measurement at all means it really
+// ran, rather than Criterion recording a fake near-zero sample for a process that errored out.
+fn bench_external_interpreters(group: &mut BenchmarkGroup<WallTime>, bench: &MicroBenchmark) {
+    if bench.iterate {
+        return;
+    }
+
+    for target in INTERPRETER_TARGETS {
+        let exe = PathBuf::from(target.path);
+
+        match Command::new(&exe).args(target.args).arg(&bench.path).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                eprintln!(
+                    "skipping {}/{}: dry run exited with {status}",
+                    target.label, bench.name
+                );
+                continue;
+            }
+            Err(e) => {
+                eprintln!("skipping {}/{}: {e}", target.label, bench.name);
+                continue;
+            }
+        }
+
+        let label = target.label;
+        let args = target.args;
+        group.bench_with_input(
+            BenchmarkId::new(label, &bench.name),
+            &bench.path,
+            move |b, file| {
+                b.iter_custom(|iters| {
+                    let start = Instant::now();
+                    for _ in 0..iters {
+                        let status = Command::new(&exe)
+                            .args(args)
+                            .arg(file)
+                            .status()
+                            .expect("failed to spawn interpreter after a successful dry run");
+                        assert!(
+                            status.success(),
+                            "{label} failed running {file:?} after a successful dry run"
+                        );
+                    }
+                    start.elapsed()
+                });
+            },
+        );
+    }
+}
+
 // Run both CPython and RustPython benchmarks for a given microbenchmark
 pub fn run_micro_benchmark(c: &mut Criterion, benchmark: MicroBenchmark) {
     let mut group = c.benchmark_group("microbenchmarks");
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+    benchmark.config.apply(&mut group);
+
+    // Iterative benchmarks already report Throughput::Elements per ITERATIONS value; for the
+    // rest, report source size so bytes/sec is comparable across benchmarks of very different
+    // sizes.
+    if !benchmark.iterate {
+        group.throughput(Throughput::Bytes(benchmark.code.len() as u64));
+    }
 
     bench_cpython_code(&mut group, &benchmark);
     bench_rustpython_code(&mut group, &benchmark);
+    bench_external_interpreters(&mut group, &benchmark);
 
     group.finish();
+
+    record_comparison(&benchmark);
+}
+
+// Pull the mean estimate Criterion just wrote for a single-value (non-iterative) benchmark
+// ID out of its `estimates.json`, so we don't have to re-measure anything ourselves.
+fn read_mean_ns(group: &str, function_id: &str, value: &str) -> Option<f64> {
+    let path = Path::new("target/criterion")
+        .join(group)
+        .join(function_id)
+        .join(value)
+        .join("new")
+        .join("estimates.json");
+    let contents = fs::read_to_string(path).ok()?;
+
+    let mean_start = contents.find("\"mean\"")?;
+    let key = "\"point_estimate\":";
+    let value_start = contents[mean_start..].find(key)? + mean_start + key.len();
+    let rest = &contents[value_start..];
+    let value_end = rest.find([',', '}'])?;
+    rest[..value_end].trim().parse().ok()
+}
+
+// After `group.finish()`, look up the cpython/rustpython estimates Criterion just wrote for
+// this benchmark and append a comparison row giving `rustpython_ns / cpython_ns`. This turns
+// the pairing of the two interpreters in the same group into a single sortable artifact
+// instead of something that has to be eyeballed out of Criterion's HTML report.
+//
+// Only non-iterative benchmarks have a single `cpython`/`rustpython` estimate to compare;
+// iterative benchmarks produce one estimate per ITERATIONS value and are skipped here.
+fn record_comparison(bench: &MicroBenchmark) {
+    if bench.iterate {
+        return;
+    }
+
+    let (Some(cpython_ns), Some(rustpython_ns)) = (
+        read_mean_ns("microbenchmarks", "cpython", &bench.name),
+        read_mean_ns("microbenchmarks", "rustpython", &bench.name),
+    ) else {
+        return;
+    };
+
+    let ratio = rustpython_ns / cpython_ns;
+    let out_dir = Path::new("target/criterion");
+
+    // `init_comparison_files` truncated both files at the start of this `cargo bench`
+    // invocation, so appending here just accumulates this run's rows, not previous runs'.
+    if let Ok(mut file) = fs::OpenOptions::new()
+        .append(true)
+        .open(out_dir.join("comparison.csv"))
+    {
+        let _ = writeln!(file, "{},{cpython_ns},{rustpython_ns},{ratio}", bench.name);
+    }
+
+    let entry = format!(
+        "{{\"name\":{:?},\"cpython_ns\":{cpython_ns},\"rustpython_ns\":{rustpython_ns},\"ratio\":{ratio}}}",
+        bench.name,
+    );
+    append_json_array_entry(&out_dir.join("comparison.json"), &entry);
+}
+
+// `comparison.json` is a real JSON array, not newline-delimited objects, so any standard JSON
+// consumer can load it directly. `init_comparison_files` seeds it with `[]`; each call here
+// rewrites the file with `entry` inserted just before the closing bracket.
+fn append_json_array_entry(path: &Path, entry: &str) {
+    let existing = fs::read_to_string(path).unwrap_or_else(|_| "[]".to_string());
+    let is_empty = existing.trim() == "[]";
+    let mut updated = existing
+        .trim_end()
+        .trim_end_matches(']')
+        .trim_end()
+        .to_string();
+    if !is_empty {
+        updated.push(',');
+    }
+    updated.push_str("\n  ");
+    updated.push_str(entry);
+    updated.push_str("\n]\n");
+    let _ = fs::write(path, updated);
+}
+
+// Reset the consolidated comparison artifacts once per `cargo bench` invocation, before any
+// benchmark runs, so they reflect only this run instead of accumulating rows across runs.
+fn init_comparison_files() {
+    let out_dir = Path::new("target/criterion");
+    let _ = fs::create_dir_all(out_dir);
+    let _ = fs::write(out_dir.join("comparison.csv"), "name,cpython_ns,rustpython_ns,ratio\n");
+    let _ = fs::write(out_dir.join("comparison.json"), "[]\n");
 }
 
 // Main function to discover and run all microbenchmarks
 pub fn criterion_benchmark(c: &mut Criterion) {
+    init_comparison_files();
+
     // Find all files in the microbenchmarks directory
     let benchmark_dir = Path::new("./benches/microbenchmarks/");
     let dirs: Vec<fs::DirEntry> = benchmark_dir
@@ -205,9 +513,13 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         .into_iter()
         .map(|p| {
             let name = p.file_name().unwrap().to_os_string();
-            let contents = fs::read_to_string(p).unwrap();
+            let contents = fs::read_to_string(&p).unwrap();
             let iterate = contents.contains("ITERATIONS");
 
+            // The `# criterion: ...` directive, if present, lives in the header above the
+            // `# ---` split, so parse it before splitting the rest of the file.
+            let config = CriterionConfig::parse(&contents);
+
             // If the file contains "# ---", split into setup and main code
             let (setup, code) = if contents.contains("# ---") {
                 let split: Vec<&str> = contents.splitn(2, "# ---").collect();
@@ -221,6 +533,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 setup,
                 code,
                 iterate,
+                config,
+                path: p,
             }
         })
         .collect();